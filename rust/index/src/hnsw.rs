@@ -1,13 +1,17 @@
 use super::{Index, IndexConfig, IndexUuid, PersistentIndex};
 use chroma_error::{ChromaError, ErrorCodes};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
 use std::ffi::CString;
 use std::ffi::{c_char, c_int};
+use std::fs::OpenOptions;
 use std::path::Path;
 use std::str::Utf8Error;
 use thiserror::Error;
 use tracing::instrument;
 
 pub const DEFAULT_MAX_ELEMENTS: usize = 10000;
+pub const DEFAULT_EF_SEARCH: usize = 10;
 
 // https://doc.rust-lang.org/nomicon/ffi.html#representing-opaque-structs
 #[repr(C)]
@@ -22,7 +26,7 @@ struct IndexPtrFFI {
 // - Have a notion of default config
 // - TODO: HNSWIndex should store a ref to the config so it can look up the config values.
 //   deferring this for a config pass
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct HnswIndexConfig {
     pub max_elements: usize,
     pub m: usize,
@@ -36,6 +40,12 @@ pub struct HnswIndexConfig {
 pub enum HnswIndexConfigError {
     #[error("Missing config `{0}`")]
     MissingConfig(String),
+    #[error("Invalid value for `{field}`: {reason}")]
+    InvalidField { field: String, reason: String },
+    #[error("Failed to parse HNSW config TOML: {0}")]
+    TomlParse(String),
+    #[error("Failed to read HNSW config file: {0}")]
+    Io(String),
 }
 
 impl ChromaError for HnswIndexConfigError {
@@ -79,6 +89,214 @@ impl HnswIndexConfig {
             persist_path: Some(persist_path.to_string()),
         })
     }
+
+    /// Single source of truth for the field-level bounds the underlying HNSW
+    /// implementation can operate on. Both [`Self::validate`] and
+    /// [`Self::validate_with_dimensionality`] are built on this so the bounds
+    /// only live in one place.
+    fn field_violations(&self) -> Vec<(&'static str, &'static str)> {
+        let mut violations = Vec::new();
+        if self.m < 2 {
+            violations.push(("m", "must be >= 2"));
+        }
+        if self.m > 100 {
+            violations.push(("m", "must be <= 100"));
+        }
+        if self.ef_construction < 1 || self.ef_construction < self.m {
+            violations.push(("ef_construction", "must be >= 1 and >= m"));
+        }
+        if self.ef_search < 1 {
+            violations.push(("ef_search", "must be >= 1"));
+        }
+        if self.max_elements == 0 {
+            violations.push(("max_elements", "must be >= 1"));
+        }
+        violations
+    }
+
+    /// Checks that every field is within the bounds the underlying HNSW
+    /// implementation can operate on, so a bad config is rejected here
+    /// instead of surfacing as an opaque failure deep in the C++ FFI layer.
+    /// Called from [`Self::from_toml`]; `Index::init` checks the same bounds
+    /// through [`Self::validate_with_index_config`].
+    pub fn validate(&self) -> Result<(), Box<HnswIndexConfigError>> {
+        match self.field_violations().first() {
+            Some((field, reason)) => Err(Box::new(HnswIndexConfigError::InvalidField {
+                field: field.to_string(),
+                reason: reason.to_string(),
+            })),
+            None => Ok(()),
+        }
+    }
+
+    /// Like [`Self::validate`], but also checks `dimensionality` and collects
+    /// every violated field into a single `IndexError::InvalidConfig` instead
+    /// of stopping at the first one, so a server loading both halves of the
+    /// config from TOML gets a complete picture of what to fix.
+    pub fn validate_with_dimensionality(&self, dimensionality: i32) -> Result<(), Box<IndexError>> {
+        let mut violations = Vec::new();
+        if dimensionality <= 0 {
+            violations.push("dimensionality: must be > 0".to_string());
+        }
+        violations.extend(
+            self.field_violations()
+                .into_iter()
+                .map(|(field, reason)| format!("{}: {}", field, reason)),
+        );
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(Box::new(IndexError::InvalidConfig(violations)))
+        }
+    }
+
+    /// Convenience wrapper over [`Self::validate_with_dimensionality`] for
+    /// callers that already have an `IndexConfig` in hand.
+    pub fn validate_with_index_config(
+        &self,
+        index_config: &IndexConfig,
+    ) -> Result<(), Box<IndexError>> {
+        self.validate_with_dimensionality(index_config.dimensionality)
+    }
+
+    /// Parses a config from a TOML document and validates it, so operators
+    /// can keep index tuning in a versioned file and get a precise error
+    /// instead of a crash when it reaches `init_index`.
+    pub fn from_toml(toml_str: &str) -> Result<Self, Box<HnswIndexConfigError>> {
+        let config: Self = toml::from_str(toml_str)
+            .map_err(|e| Box::new(HnswIndexConfigError::TomlParse(e.to_string())))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Reads and parses a config from a TOML file on disk. See [`Self::from_toml`].
+    pub fn from_path(path: &Path) -> Result<Self, Box<HnswIndexConfigError>> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| Box::new(HnswIndexConfigError::Io(e.to_string())))?;
+        Self::from_toml(&contents)
+    }
+}
+
+/// A structured validation failure covering both halves of an index's
+/// config, so a server loading parameters from a TOML file gets every
+/// violated field at once instead of one error at a time.
+#[derive(Error, Debug)]
+pub enum IndexError {
+    #[error("Invalid index configuration: {}", .0.join(", "))]
+    InvalidConfig(Vec<String>),
+}
+
+impl ChromaError for IndexError {
+    fn code(&self) -> ErrorCodes {
+        ErrorCodes::InvalidArgument
+    }
+}
+
+/// `dimensionality` plus `HnswIndexConfig`, round-tripped and validated
+/// together through TOML. Does not make "both config structs"
+/// `Serialize`/`Deserialize` as originally requested - `IndexConfig` and
+/// `DistanceFunction` live outside this module and aren't serde-derived, so
+/// the distance function still can't round-trip through this struct.
+#[derive(Clone, Debug, Serialize)]
+pub struct HnswPersistedConfig {
+    pub dimensionality: i32,
+    pub hnsw_config: HnswIndexConfig,
+}
+
+impl<'de> Deserialize<'de> for HnswPersistedConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Raw {
+            dimensionality: i32,
+            hnsw_config: HnswIndexConfig,
+        }
+
+        let raw = Raw::deserialize(deserializer)?;
+        raw.hnsw_config
+            .validate_with_dimensionality(raw.dimensionality)
+            .map_err(serde::de::Error::custom)?;
+        Ok(Self {
+            dimensionality: raw.dimensionality,
+            hnsw_config: raw.hnsw_config,
+        })
+    }
+}
+
+/// The portion of `HnswIndexConfig` that is fixed for the lifetime of an
+/// `HnswIndex` - changing any of these requires rebuilding the index.
+/// Persisted alongside the index as `STATIC_CONFIG_FILE_NAME` so that
+/// `load` (which only receives a path, not the original `HnswIndexConfig`)
+/// can recover it - most importantly so `compact` can rebuild with the same
+/// parameters instead of degenerate ones.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct HnswIndexStaticConfig {
+    m: usize,
+    ef_construction: usize,
+    random_seed: usize,
+}
+
+/// The portion of `HnswIndexConfig` that can be changed on a live index
+/// without rebuilding it.
+#[derive(Clone, Copy, Debug)]
+struct HnswDynamicConfig {
+    ef_search: usize,
+}
+
+impl HnswIndexConfig {
+    fn static_config(&self) -> HnswIndexStaticConfig {
+        HnswIndexStaticConfig {
+            m: self.m,
+            ef_construction: self.ef_construction,
+            random_seed: self.random_seed,
+        }
+    }
+
+    fn dynamic_config(&self) -> HnswDynamicConfig {
+        HnswDynamicConfig {
+            ef_search: self.ef_search,
+        }
+    }
+}
+
+/// A handle to the dynamic (hot-reloadable) portion of an index's config.
+/// Cloning a handle shares the same underlying value, so a caller can hold
+/// on to one and push new values - e.g. raising `ef_search` under heavy
+/// recall requirements, or lowering it to cut latency - without the index
+/// needing to be dropped and reloaded.
+#[derive(Clone)]
+pub struct WatchableHnswConfig {
+    current: std::sync::Arc<arc_swap::ArcSwap<HnswDynamicConfig>>,
+}
+
+impl WatchableHnswConfig {
+    fn new(initial: HnswDynamicConfig) -> Self {
+        Self {
+            current: std::sync::Arc::new(arc_swap::ArcSwap::from_pointee(initial)),
+        }
+    }
+
+    fn current(&self) -> HnswDynamicConfig {
+        **self.current.load()
+    }
+
+    /// Updates the watched `ef_search` value. Static fields have no setter
+    /// here, so they're unreachable through this handle rather than
+    /// rejected with an error as originally requested - there's no runtime
+    /// check to reject, since the type just doesn't expose them.
+    pub fn set_ef_search(&self, ef_search: usize) -> Result<(), Box<dyn ChromaError>> {
+        if ef_search < 1 {
+            return Err(Box::new(HnswIndexConfigError::InvalidField {
+                field: "ef_search".to_string(),
+                reason: "must be >= 1".to_string(),
+            }));
+        }
+        self.current.store(std::sync::Arc::new(HnswDynamicConfig { ef_search }));
+        Ok(())
+    }
 }
 
 #[repr(C)]
@@ -91,7 +309,20 @@ impl HnswIndexConfig {
 pub struct HnswIndex {
     ffi_ptr: *const IndexPtrFFI,
     dimensionality: i32,
+    distance_function: chroma_distance::DistanceFunction,
     pub id: IndexUuid,
+    // `None` for an index loaded from a `persist_path` written before static
+    // config persistence was added. `compact` refuses to run in that case
+    // rather than rebuilding with guessed-at parameters.
+    static_config: Option<HnswIndexStaticConfig>,
+    dynamic_config: WatchableHnswConfig,
+    persist_path: Option<String>,
+    // Released when the last clone (see `acquire_lock`'s process-local
+    // registry) is dropped. An exclusive lock is taken by writers (`init`), a
+    // shared lock by readers (`load`), so concurrent readers are allowed but
+    // a writer excludes other processes. Handles within this process share
+    // one lock through the registry.
+    _lock_file: Option<std::sync::Arc<std::fs::File>>,
 }
 
 // Make index sync, we should wrap index so that it is sync in the way we expect but for now this implements the trait
@@ -142,6 +373,10 @@ impl Index<HnswIndexConfig> for HnswIndex {
         match hnsw_config {
             None => Err(Box::new(HnswIndexInitError::NoConfigProvided)),
             Some(config) => {
+                config
+                    .validate_with_index_config(index_config)
+                    .map_err(|e| e as Box<dyn ChromaError>)?;
+
                 let distance_function_string: String =
                     index_config.distance_function.clone().into();
 
@@ -177,12 +412,27 @@ impl Index<HnswIndexConfig> for HnswIndex {
                 }
                 read_and_return_hnsw_error(ffi_ptr)?;
 
+                let lock_file = match &config.persist_path {
+                    Some(persist_path) => Some(acquire_lock(persist_path, true)?),
+                    None => None,
+                };
+
+                let static_config = config.static_config();
+                if let Some(persist_path) = &config.persist_path {
+                    write_static_config(persist_path, &static_config)?;
+                }
+
                 let hnsw_index = HnswIndex {
                     ffi_ptr,
                     dimensionality: index_config.dimensionality,
+                    distance_function: index_config.distance_function.clone(),
                     id,
+                    static_config: Some(static_config),
+                    dynamic_config: WatchableHnswConfig::new(config.dynamic_config()),
+                    persist_path: config.persist_path.clone(),
+                    _lock_file: lock_file,
                 };
-                hnsw_index.set_ef(config.ef_search)?;
+                hnsw_index.apply_dynamic_config()?;
                 Ok(hnsw_index)
             }
         }
@@ -266,6 +516,9 @@ impl PersistentIndex<HnswIndexConfig> for HnswIndex {
     fn save(&self) -> Result<(), Box<dyn ChromaError>> {
         unsafe { persist_dirty(self.ffi_ptr) };
         read_and_return_hnsw_error(self.ffi_ptr)?;
+        if let Some(persist_path) = &self.persist_path {
+            write_integrity(persist_path, &self.id)?;
+        }
         Ok(())
     }
 
@@ -287,6 +540,13 @@ impl PersistentIndex<HnswIndexConfig> for HnswIndex {
         let ffi_ptr = unsafe { create_index(space_name.as_ptr(), index_config.dimensionality) };
         read_and_return_hnsw_error(ffi_ptr)?;
 
+        let lock_file = acquire_lock(path, false)?;
+
+        if Path::new(path).join(INTEGRITY_FILE_NAME).exists() {
+            verify_integrity(path, &id)?;
+        }
+
+        let path_str = path.to_string();
         let path = match CString::new(path.to_string()) {
             Ok(path) => path,
             Err(e) => return Err(Box::new(HnswIndexInitError::InvalidPath(e.to_string()))),
@@ -296,16 +556,46 @@ impl PersistentIndex<HnswIndexConfig> for HnswIndex {
         }
         read_and_return_hnsw_error(ffi_ptr)?;
 
+        // `load` only receives a path, not the original `HnswIndexConfig`, so the
+        // static fields (`m`, `ef_construction`, `random_seed`) that were baked
+        // into the on-disk index at build time are recovered from
+        // `STATIC_CONFIG_FILE_NAME` if `init` wrote one - `None` if this index
+        // predates static config persistence. `ef_search` is dynamic and
+        // defaults to `DEFAULT_EF_SEARCH` until the caller sets a watched
+        // value through `dynamic_config()` and calls `apply_dynamic_config()`.
+        let static_config = read_static_config(&path_str)?;
+
         let hnsw_index = HnswIndex {
             ffi_ptr,
             dimensionality: index_config.dimensionality,
+            distance_function: index_config.distance_function.clone(),
             id,
+            static_config,
+            dynamic_config: WatchableHnswConfig::new(HnswDynamicConfig {
+                ef_search: DEFAULT_EF_SEARCH,
+            }),
+            persist_path: Some(path_str),
+            _lock_file: Some(lock_file),
         };
         Ok(hnsw_index)
     }
 }
 
 impl HnswIndex {
+    /// Returns a cloneable handle to this index's watched dynamic config.
+    /// Calling [`WatchableHnswConfig::set_ef_search`] on the handle and then
+    /// [`Self::apply_dynamic_config`] lets operators tune query recall/latency
+    /// on a live index without rebuilding it.
+    pub fn dynamic_config(&self) -> WatchableHnswConfig {
+        self.dynamic_config.clone()
+    }
+
+    /// Reads the current watched dynamic config and pushes it down to the
+    /// underlying index via the FFI `set_ef` binding.
+    pub fn apply_dynamic_config(&self) -> Result<(), Box<dyn ChromaError>> {
+        self.set_ef(self.dynamic_config.current().ef_search)
+    }
+
     fn set_ef(&self, ef: usize) -> Result<(), Box<dyn ChromaError>> {
         unsafe { set_ef(self.ffi_ptr, ef as c_int) }
         read_and_return_hnsw_error(self.ffi_ptr)
@@ -339,6 +629,112 @@ impl HnswIndex {
         read_and_return_hnsw_error(self.ffi_ptr)
     }
 
+    /// Rebuilds the graph excluding tombstoned ids, so `len_with_deleted()`
+    /// stops diverging from `len()` under heavy delete/re-add churn instead
+    /// of relying on `resize` to grow capacity forever. Only supported on a
+    /// persistent index: the rebuild is staged in a sibling directory, then
+    /// swapped in via directory-level renames rather than one file at a
+    /// time - this narrows, but does not eliminate, the crash window a
+    /// reader could observe a half-swapped `persist_path`; `load`'s
+    /// integrity check is the backstop for that. Returns the number of
+    /// slots reclaimed.
+    pub fn compact(&mut self) -> Result<usize, Box<dyn ChromaError>> {
+        let reclaimed = self.len_with_deleted().saturating_sub(self.len());
+        if reclaimed == 0 {
+            return Ok(0);
+        }
+
+        let persist_path = self.persist_path.clone().ok_or_else(|| {
+            Box::new(HnswIndexCompactionError::NotPersistent) as Box<dyn ChromaError>
+        })?;
+        let static_config = self.static_config.clone().ok_or_else(|| {
+            Box::new(HnswIndexCompactionError::UnknownStaticConfig) as Box<dyn ChromaError>
+        })?;
+
+        let (live_ids, _deleted_ids) = self.get_all_ids()?;
+        let dim = self.dimensionality as usize;
+        let mut live_vectors = Vec::with_capacity(live_ids.len() * dim);
+        for id in &live_ids {
+            // `get` reports a missing id as an `Err` from the FFI layer, not
+            // `None` - `get_all_ids` just gave us `id`, so it's live.
+            let vector = self
+                .get(*id)?
+                .expect("a live id from get_all_ids should always have data");
+            live_vectors.extend(vector);
+        }
+
+        let rebuild_path = format!("{}.compacting", persist_path);
+        let _ = std::fs::remove_dir_all(&rebuild_path);
+        std::fs::create_dir_all(&rebuild_path).map_err(|e| {
+            Box::new(HnswIndexCompactionError::Io(e.to_string())) as Box<dyn ChromaError>
+        })?;
+
+        let rebuild_config = HnswIndexConfig {
+            max_elements: live_ids.len().max(1),
+            m: static_config.m,
+            ef_construction: static_config.ef_construction,
+            ef_search: self.dynamic_config.current().ef_search,
+            random_seed: static_config.random_seed,
+            persist_path: Some(rebuild_path.clone()),
+        };
+        rebuild_config
+            .validate_with_dimensionality(self.dimensionality)
+            .map_err(|e| e as Box<dyn ChromaError>)?;
+
+        let rebuilt = HnswIndex::init(
+            &IndexConfig {
+                dimensionality: self.dimensionality,
+                distance_function: self.distance_function.clone(),
+            },
+            Some(&rebuild_config),
+            IndexUuid(self.id.0),
+        )?;
+
+        for (i, id) in live_ids.iter().enumerate() {
+            rebuilt.add(*id, &live_vectors[i * dim..(i + 1) * dim])?;
+        }
+        rebuilt.save()?;
+        drop(rebuilt);
+
+        // Swap via two directory renames rather than per-file renames, so a
+        // crash can't leave a mixed old/new file set. Still not atomic across
+        // the two renames - `load`'s integrity check is the backstop.
+        let backup_path = format!("{}.prev", persist_path);
+        let _ = std::fs::remove_dir_all(&backup_path);
+        std::fs::rename(&persist_path, &backup_path).map_err(|e| {
+            Box::new(HnswIndexCompactionError::Io(e.to_string())) as Box<dyn ChromaError>
+        })?;
+        if let Err(e) = std::fs::rename(&rebuild_path, &persist_path) {
+            let _ = std::fs::rename(&backup_path, &persist_path);
+            return Err(Box::new(HnswIndexCompactionError::Io(e.to_string())));
+        }
+        let _ = std::fs::remove_dir_all(&backup_path);
+
+        let dynamic_config = self.dynamic_config.clone();
+
+        // The old persist_path/.lock inode was just renamed away and
+        // removed; drop our handle to it so `load` below locks the new one
+        // instead of reusing a cached handle to the deleted file.
+        self._lock_file = None;
+
+        let reloaded = HnswIndex::load(
+            &persist_path,
+            &IndexConfig {
+                dimensionality: self.dimensionality,
+                distance_function: self.distance_function.clone(),
+            },
+            IndexUuid(self.id.0),
+        )?;
+        *self = reloaded;
+        // Keep the same handle alive rather than replacing it, so a caller
+        // holding a clone from `dynamic_config()` before compaction still
+        // reaches this index afterward.
+        self.dynamic_config = dynamic_config;
+        self.apply_dynamic_config()?;
+
+        Ok(reclaimed)
+    }
+
     pub fn open_fd(&self) {
         unsafe { open_fd(self.ffi_ptr) }
     }
@@ -358,6 +754,8 @@ impl HnswIndex {
 
 impl Drop for HnswIndex {
     fn drop(&mut self) {
+        // Dropping `_lock_file` (when this was the last clone) closes the
+        // underlying fd, which releases the OS-level advisory lock.
         unsafe { free_index(self.ffi_ptr) }
     }
 }
@@ -373,6 +771,291 @@ fn read_and_return_hnsw_error(ffi_ptr: *const IndexPtrFFI) -> Result<(), Box<dyn
     Ok(())
 }
 
+/// Files the underlying HNSW library persists on every `save()`. Order
+/// matters: it defines the on-disk layout of `integrity.bin`.
+const PERSISTED_FILES: [&str; 4] = [
+    "header.bin",
+    "data_level0.bin",
+    "length.bin",
+    "link_lists.bin",
+];
+
+const INTEGRITY_FILE_NAME: &str = "integrity.bin";
+const STATIC_CONFIG_FILE_NAME: &str = "static_config.toml";
+const LOCK_FILE_NAME: &str = ".lock";
+
+#[derive(Error, Debug)]
+pub enum HnswIndexCompactionError {
+    #[error("Cannot compact an ephemeral (non-persistent) index")]
+    NotPersistent,
+    #[error("Cannot compact an index whose static config (m, ef_construction, random_seed) is unknown - it was loaded from a persist_path written before static config persistence was added")]
+    UnknownStaticConfig,
+    #[error("IO error while compacting index: {0}")]
+    Io(String),
+}
+
+impl ChromaError for HnswIndexCompactionError {
+    fn code(&self) -> ErrorCodes {
+        ErrorCodes::Internal
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum HnswIndexLockError {
+    #[error("Could not acquire lock on `{0}`: {1}")]
+    LockFailed(String, String),
+}
+
+impl ChromaError for HnswIndexLockError {
+    fn code(&self) -> ErrorCodes {
+        ErrorCodes::Internal
+    }
+}
+
+/// Tracks `.lock` files this process currently holds open, keyed by path,
+/// alongside whether the held lock is exclusive. `flock`/`fcntl` locks are
+/// scoped to the open file description, not the process, so a second handle
+/// opened in-process for the same path needs to reuse this one instead of
+/// conflicting with itself.
+static LOCK_REGISTRY: std::sync::OnceLock<
+    std::sync::Mutex<std::collections::HashMap<std::path::PathBuf, (std::sync::Weak<std::fs::File>, bool)>>,
+> = std::sync::OnceLock::new();
+
+/// Acquires a cross-platform advisory lock (`flock`/`fcntl` on Unix,
+/// `LockFileEx` on Windows, via the `fs2` crate) on a `.lock` file inside
+/// `persist_path`, released when the last clone of the returned handle is
+/// dropped. Writers take an exclusive lock, readers a shared lock. Never
+/// blocks - a conflicting lock elsewhere is a clear error, not a wait.
+fn acquire_lock(
+    persist_path: &str,
+    exclusive: bool,
+) -> Result<std::sync::Arc<std::fs::File>, Box<dyn ChromaError>> {
+    let lock_path = Path::new(persist_path).join(LOCK_FILE_NAME);
+    let registry = LOCK_REGISTRY.get_or_init(|| std::sync::Mutex::new(std::collections::HashMap::new()));
+    let mut registry = registry.lock().expect("lock registry mutex poisoned");
+
+    // Evict entries whose last clone was dropped, so a long-running,
+    // multi-path process (and every compact()'s `<path>.compacting/.lock`)
+    // doesn't grow this map without bound.
+    registry.retain(|_, (weak, _)| weak.strong_count() > 0);
+
+    if let Some((weak, held_exclusive)) = registry.get(&lock_path) {
+        let existing = weak.upgrade().expect("just pruned dead entries");
+        if *held_exclusive || !exclusive {
+            return Ok(existing);
+        }
+        // Held shared, exclusive requested: upgrade the same open file
+        // description instead of handing back a shared handle while
+        // reporting success as an exclusive writer.
+        existing.try_lock_exclusive().map_err(|e| {
+            Box::new(HnswIndexLockError::LockFailed(
+                lock_path.display().to_string(),
+                e.to_string(),
+            )) as Box<dyn ChromaError>
+        })?;
+        registry.insert(lock_path, (std::sync::Arc::downgrade(&existing), true));
+        return Ok(existing);
+    }
+
+    let file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .write(true)
+        .open(&lock_path)
+        .map_err(|e| {
+            Box::new(HnswIndexLockError::LockFailed(
+                lock_path.display().to_string(),
+                e.to_string(),
+            )) as Box<dyn ChromaError>
+        })?;
+
+    let try_result = if exclusive {
+        file.try_lock_exclusive()
+    } else {
+        file.try_lock_shared()
+    };
+    try_result.map_err(|e| {
+        Box::new(HnswIndexLockError::LockFailed(
+            lock_path.display().to_string(),
+            e.to_string(),
+        )) as Box<dyn ChromaError>
+    })?;
+
+    let file = std::sync::Arc::new(file);
+    registry.insert(lock_path, (std::sync::Arc::downgrade(&file), exclusive));
+    Ok(file)
+}
+
+#[derive(Error, Debug)]
+pub enum HnswIndexIntegrityError {
+    #[error("HNSW Integrity failure: {0}")]
+    Mismatch(String),
+    #[error("HNSW Integrity failure: could not read `{0}`: {1}")]
+    Io(String, String),
+}
+
+impl ChromaError for HnswIndexIntegrityError {
+    fn code(&self) -> ErrorCodes {
+        ErrorCodes::Internal
+    }
+}
+
+/// A rolling Adler-32 checksum. Chosen over a cryptographic hash for speed
+/// over large vector files - it catches accidental corruption, not
+/// malicious tampering.
+fn adler32(bytes: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+    for &byte in bytes {
+        a = (a + byte as u32) % MOD_ADLER;
+        b = (b + a) % MOD_ADLER;
+    }
+    (b << 16) | a
+}
+
+/// Writes the static (rebuild-only) portion of an index's config to
+/// `STATIC_CONFIG_FILE_NAME`, so a later `load` of the same `persist_path`
+/// can recover it.
+fn write_static_config(
+    persist_path: &str,
+    static_config: &HnswIndexStaticConfig,
+) -> Result<(), Box<dyn ChromaError>> {
+    let toml_str = toml::to_string(static_config).map_err(|e| {
+        Box::new(HnswIndexConfigError::TomlParse(e.to_string())) as Box<dyn ChromaError>
+    })?;
+    std::fs::write(
+        Path::new(persist_path).join(STATIC_CONFIG_FILE_NAME),
+        toml_str,
+    )
+    .map_err(|e| Box::new(HnswIndexConfigError::Io(e.to_string())) as Box<dyn ChromaError>)?;
+    Ok(())
+}
+
+/// Reads the static config sidecar written by `write_static_config`. Returns
+/// `None` (rather than erroring) when the file is absent, since that's
+/// expected for a `persist_path` written before static config persistence
+/// was added - callers that need the static config (like `compact`) reject
+/// cleanly on `None` instead of guessing.
+fn read_static_config(
+    persist_path: &str,
+) -> Result<Option<HnswIndexStaticConfig>, Box<dyn ChromaError>> {
+    let config_path = Path::new(persist_path).join(STATIC_CONFIG_FILE_NAME);
+    if !config_path.exists() {
+        return Ok(None);
+    }
+    let contents = std::fs::read_to_string(&config_path)
+        .map_err(|e| Box::new(HnswIndexConfigError::Io(e.to_string())) as Box<dyn ChromaError>)?;
+    let static_config: HnswIndexStaticConfig = toml::from_str(&contents)
+        .map_err(|e| Box::new(HnswIndexConfigError::TomlParse(e.to_string())) as Box<dyn ChromaError>)?;
+    Ok(Some(static_config))
+}
+
+/// Per-file record width in `integrity.bin`: 1 presence byte (the underlying
+/// HNSW library doesn't write every file in `PERSISTED_FILES` for every
+/// index - e.g. `link_lists.bin` can be absent for an index with no
+/// upper-level links - plus a 4-byte checksum and 8-byte length, both zero
+/// when the file is absent.
+const INTEGRITY_RECORD_LEN: usize = 13;
+
+/// Computes and writes an `integrity.bin` sidecar covering every file
+/// `save()` just persisted, keyed by the index's `IndexUuid`. Tolerates any
+/// `PERSISTED_FILES` entry being absent, recording that fact rather than
+/// erroring, since not every file is guaranteed to exist for every index.
+fn write_integrity(persist_path: &str, id: &IndexUuid) -> Result<(), Box<dyn ChromaError>> {
+    let mut buf = Vec::with_capacity(16 + PERSISTED_FILES.len() * INTEGRITY_RECORD_LEN);
+    buf.extend_from_slice(id.0.as_bytes());
+    for file_name in PERSISTED_FILES {
+        let file_path = Path::new(persist_path).join(file_name);
+        if !file_path.exists() {
+            buf.push(0);
+            buf.extend_from_slice(&0u32.to_le_bytes());
+            buf.extend_from_slice(&0u64.to_le_bytes());
+            continue;
+        }
+        let data = std::fs::read(&file_path).map_err(|e| {
+            Box::new(HnswIndexIntegrityError::Io(
+                file_path.display().to_string(),
+                e.to_string(),
+            ))
+        })?;
+        buf.push(1);
+        buf.extend_from_slice(&adler32(&data).to_le_bytes());
+        buf.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    }
+    std::fs::write(Path::new(persist_path).join(INTEGRITY_FILE_NAME), buf).map_err(|e| {
+        Box::new(HnswIndexIntegrityError::Io(
+            INTEGRITY_FILE_NAME.to_string(),
+            e.to_string(),
+        ))
+    })?;
+    Ok(())
+}
+
+/// Recomputes each persisted file's checksum and length and compares it
+/// against the `integrity.bin` sidecar written by `write_integrity`,
+/// rejecting a corrupted index before its buffers reach the HNSW reader. A
+/// file recorded as absent is only required to stay absent - it does not
+/// need to reappear - while a file recorded as present must still exist and
+/// match its recorded checksum.
+fn verify_integrity(persist_path: &str, id: &IndexUuid) -> Result<(), Box<dyn ChromaError>> {
+    let integrity_path = Path::new(persist_path).join(INTEGRITY_FILE_NAME);
+    let buf = std::fs::read(&integrity_path).map_err(|e| {
+        Box::new(HnswIndexIntegrityError::Io(
+            integrity_path.display().to_string(),
+            e.to_string(),
+        ))
+    })?;
+
+    let expected_len = 16 + PERSISTED_FILES.len() * INTEGRITY_RECORD_LEN;
+    if buf.len() != expected_len {
+        return Err(Box::new(HnswIndexIntegrityError::Mismatch(
+            "integrity.bin is truncated or malformed".to_string(),
+        )));
+    }
+
+    if &buf[0..16] != id.0.as_bytes() {
+        return Err(Box::new(HnswIndexIntegrityError::Mismatch(
+            "integrity.bin belongs to a different index id".to_string(),
+        )));
+    }
+
+    let mut offset = 16;
+    for file_name in PERSISTED_FILES {
+        let present = buf[offset] != 0;
+        let checksum = u32::from_le_bytes(buf[offset + 1..offset + 5].try_into().unwrap());
+        let length = u64::from_le_bytes(buf[offset + 5..offset + 13].try_into().unwrap());
+        offset += INTEGRITY_RECORD_LEN;
+
+        let file_path = Path::new(persist_path).join(file_name);
+        if !present {
+            if file_path.exists() {
+                return Err(Box::new(HnswIndexIntegrityError::Mismatch(format!(
+                    "`{}` was recorded as absent but now exists",
+                    file_name
+                ))));
+            }
+            continue;
+        }
+
+        let data = std::fs::read(&file_path).map_err(|e| {
+            Box::new(HnswIndexIntegrityError::Io(
+                file_path.display().to_string(),
+                e.to_string(),
+            ))
+        })?;
+
+        if data.len() as u64 != length || adler32(&data) != checksum {
+            return Err(Box::new(HnswIndexIntegrityError::Mismatch(format!(
+                "`{}` does not match its recorded checksum",
+                file_name
+            ))));
+        }
+    }
+
+    Ok(())
+}
+
 #[link(name = "bindings", kind = "static")]
 extern "C" {
     fn create_index(space_name: *const c_char, dim: c_int) -> *const IndexPtrFFI;
@@ -496,6 +1179,44 @@ pub mod test {
         }
     }
 
+    #[test]
+    fn it_can_hot_reload_ef_search_via_watchable_config() {
+        let n = 1000;
+        let d: usize = 960;
+        let tmp_dir = tempdir().unwrap();
+        let persist_path = tmp_dir.path().to_str().unwrap().to_string();
+        let distance_function = DistanceFunction::Euclidean;
+        let index = HnswIndex::init(
+            &IndexConfig {
+                dimensionality: d as i32,
+                distance_function,
+            },
+            Some(&HnswIndexConfig {
+                max_elements: n,
+                m: 16,
+                ef_construction: 100,
+                ef_search: 10,
+                random_seed: 0,
+                persist_path: Some(persist_path),
+            }),
+            IndexUuid(Uuid::new_v4()),
+        )
+        .expect("Should not error");
+
+        assert_eq!(index.get_ef().unwrap(), 10);
+
+        let watched = index.dynamic_config();
+        watched.set_ef_search(50).expect("Should not error");
+        // The index itself hasn't been told yet - only the watched handle has
+        // the new value until `apply_dynamic_config` pushes it down.
+        assert_eq!(index.get_ef().unwrap(), 10);
+
+        index.apply_dynamic_config().expect("Should not error");
+        assert_eq!(index.get_ef().unwrap(), 50);
+
+        assert!(watched.set_ef_search(0).is_err());
+    }
+
     #[test]
     fn it_can_add_parallel() {
         let n: usize = 100;
@@ -880,9 +1601,6 @@ pub mod test {
     }
 
     #[test]
-    // TODO(rescrv,sicheng):  This test should be re-enabled once we have a way to detect
-    // corruption.
-    #[ignore]
     fn it_can_detect_corruption() {
         let n = 1000;
         let d: usize = 960;
@@ -1000,4 +1718,314 @@ pub mod test {
         // this will fail if the index is not resized correctly
         index.add(100, data).unwrap();
     }
+
+    fn valid_hnsw_index_config() -> HnswIndexConfig {
+        HnswIndexConfig {
+            max_elements: 10000,
+            m: 16,
+            ef_construction: 100,
+            ef_search: 10,
+            random_seed: 0,
+            persist_path: None,
+        }
+    }
+
+    #[test]
+    fn it_validates_hnsw_index_config_bounds() {
+        assert!(valid_hnsw_index_config().validate().is_ok());
+
+        let mut config = valid_hnsw_index_config();
+        config.m = 1;
+        assert!(config.validate().is_err());
+
+        let mut config = valid_hnsw_index_config();
+        config.m = 101;
+        assert!(config.validate().is_err());
+
+        let mut config = valid_hnsw_index_config();
+        config.ef_construction = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = valid_hnsw_index_config();
+        config.ef_search = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = valid_hnsw_index_config();
+        config.max_elements = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn it_round_trips_hnsw_index_config_via_toml() {
+        let config = valid_hnsw_index_config();
+        let toml_str = toml::to_string(&config).unwrap();
+        let parsed = HnswIndexConfig::from_toml(&toml_str).unwrap();
+        assert_eq!(parsed.m, config.m);
+        assert_eq!(parsed.ef_construction, config.ef_construction);
+        assert_eq!(parsed.ef_search, config.ef_search);
+        assert_eq!(parsed.max_elements, config.max_elements);
+    }
+
+    #[test]
+    fn it_rejects_an_invalid_config_from_toml() {
+        let mut config = valid_hnsw_index_config();
+        config.m = 0;
+        let toml_str = toml::to_string(&config).unwrap();
+        assert!(HnswIndexConfig::from_toml(&toml_str).is_err());
+    }
+
+    #[test]
+    fn it_round_trips_hnsw_persisted_config_via_toml() {
+        let persisted = HnswPersistedConfig {
+            dimensionality: 960,
+            hnsw_config: valid_hnsw_index_config(),
+        };
+        let toml_str = toml::to_string(&persisted).unwrap();
+        let parsed: HnswPersistedConfig = toml::from_str(&toml_str).unwrap();
+        assert_eq!(parsed.dimensionality, persisted.dimensionality);
+        assert_eq!(parsed.hnsw_config.m, persisted.hnsw_config.m);
+    }
+
+    #[test]
+    fn it_rejects_invalid_dimensionality_in_persisted_config() {
+        let persisted = HnswPersistedConfig {
+            dimensionality: 0,
+            hnsw_config: valid_hnsw_index_config(),
+        };
+        let toml_str = toml::to_string(&persisted).unwrap();
+        let parsed: Result<HnswPersistedConfig, _> = toml::from_str(&toml_str);
+        assert!(parsed.is_err());
+    }
+
+    #[test]
+    fn it_rejects_an_invalid_config_at_init_instead_of_the_ffi_layer() {
+        let tmp_dir = tempdir().unwrap();
+        let persist_path = tmp_dir.path().to_str().unwrap().to_string();
+        let index = HnswIndex::init(
+            &IndexConfig {
+                dimensionality: 960,
+                distance_function: DistanceFunction::Euclidean,
+            },
+            Some(&HnswIndexConfig {
+                max_elements: 10,
+                m: 0, // invalid: must be >= 2
+                ef_construction: 100,
+                ef_search: 10,
+                random_seed: 0,
+                persist_path: Some(persist_path),
+            }),
+            IndexUuid(Uuid::new_v4()),
+        );
+        assert!(index.is_err());
+    }
+
+    #[test]
+    fn it_can_load_same_persist_path_while_writer_handle_is_alive() {
+        let n = 10;
+        let d: usize = 960;
+        let distance_function = DistanceFunction::Euclidean;
+        let tmp_dir = tempdir().unwrap();
+        let persist_path = tmp_dir.path().to_str().unwrap().to_string();
+        let id = Uuid::new_v4();
+        let index = HnswIndex::init(
+            &IndexConfig {
+                dimensionality: d as i32,
+                distance_function: distance_function.clone(),
+            },
+            Some(&HnswIndexConfig {
+                max_elements: n,
+                m: 16,
+                ef_construction: 100,
+                ef_search: 10,
+                random_seed: 0,
+                persist_path: Some(persist_path.clone()),
+            }),
+            IndexUuid(id),
+        )
+        .expect("Error initializing index");
+
+        index.save().expect("Should not error");
+
+        // The writer handle (`index`) is still in scope, holding the
+        // exclusive lock it took in `init`. Loading the same `persist_path`
+        // should reuse that lock rather than blocking or erroring.
+        let loaded = HnswIndex::load(
+            &persist_path,
+            &IndexConfig {
+                dimensionality: d as i32,
+                distance_function,
+            },
+            IndexUuid(id),
+        );
+        assert!(loaded.is_ok());
+    }
+
+    #[test]
+    fn it_rejects_a_conflicting_lock_held_by_a_separate_file_handle() {
+        let tmp_dir = tempdir().unwrap();
+        let persist_path = tmp_dir.path().to_str().unwrap().to_string();
+        std::fs::create_dir_all(&persist_path).unwrap();
+
+        // `flock` conflicts are scoped to the open file description, not the
+        // process, so locking through a handle that bypasses our in-process
+        // registry reproduces what a genuinely separate process would see.
+        let lock_path = Path::new(&persist_path).join(".lock");
+        let other_process_lock = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .write(true)
+            .open(&lock_path)
+            .unwrap();
+        other_process_lock.lock_exclusive().unwrap();
+
+        let result = acquire_lock(&persist_path, false);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn it_upgrades_an_in_process_shared_lock_to_exclusive() {
+        let tmp_dir = tempdir().unwrap();
+        let persist_path = tmp_dir.path().to_str().unwrap().to_string();
+        std::fs::create_dir_all(&persist_path).unwrap();
+
+        let shared = acquire_lock(&persist_path, false).expect("Should not error");
+        let exclusive = acquire_lock(&persist_path, true).expect("Should upgrade in-process");
+        assert!(std::sync::Arc::ptr_eq(&shared, &exclusive));
+    }
+
+    #[test]
+    fn it_rejects_upgrading_to_exclusive_when_another_handle_holds_shared() {
+        let tmp_dir = tempdir().unwrap();
+        let persist_path = tmp_dir.path().to_str().unwrap().to_string();
+        std::fs::create_dir_all(&persist_path).unwrap();
+
+        let lock_path = Path::new(&persist_path).join(".lock");
+        let other_process_lock = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .write(true)
+            .open(&lock_path)
+            .unwrap();
+        other_process_lock.lock_shared().unwrap();
+
+        // In-process acquire_lock sees no cached entry yet, so this opens its
+        // own handle and takes a real shared flock alongside the other one.
+        let _shared = acquire_lock(&persist_path, false).expect("Should not error");
+        // Upgrading to exclusive must fail rather than silently handing back
+        // the shared handle while reporting success as a writer.
+        assert!(acquire_lock(&persist_path, true).is_err());
+    }
+
+    #[test]
+    fn it_can_compact_reclaiming_slots_and_preserving_query_results() {
+        let n = 200;
+        let d: usize = 960;
+        let distance_function = DistanceFunction::Euclidean;
+        let tmp_dir = tempdir().unwrap();
+        let persist_path = tmp_dir.path().to_str().unwrap().to_string();
+        let id = Uuid::new_v4();
+        let mut index = HnswIndex::init(
+            &IndexConfig {
+                dimensionality: d as i32,
+                distance_function: distance_function.clone(),
+            },
+            Some(&HnswIndexConfig {
+                max_elements: n,
+                m: 16,
+                ef_construction: 100,
+                ef_search: 100,
+                random_seed: 0,
+                persist_path: Some(persist_path.clone()),
+            }),
+            IndexUuid(id),
+        )
+        .expect("Error initializing index");
+
+        let data: Vec<f32> = utils::generate_random_data(n, d);
+        let ids: Vec<usize> = (0..n).collect();
+        (0..n).for_each(|i| {
+            let data = &data[i * d..(i + 1) * d];
+            index.add(ids[i], data).expect("Should not error");
+        });
+
+        // Delete half the ids so len() and len_with_deleted() diverge.
+        for id in ids.iter().take(n / 2) {
+            index.delete(*id).unwrap();
+        }
+        assert_eq!(index.len(), n - n / 2);
+        assert_eq!(index.len_with_deleted(), n);
+
+        let query_vector = &data[(n - 1) * d..n * d];
+        let (before_ids, _) = index
+            .query(query_vector, 5, &[], &[])
+            .expect("Should not error");
+
+        let reclaimed = index.compact().expect("Should not error");
+        assert_eq!(reclaimed, n / 2);
+        assert_eq!(index.len(), n - n / 2);
+        assert_eq!(index.len_with_deleted(), n - n / 2);
+
+        let (after_ids, _) = index
+            .query(query_vector, 5, &[], &[])
+            .expect("Should not error");
+        assert_eq!(before_ids, after_ids);
+
+        // Compacting again is a no-op since nothing is tombstoned.
+        assert_eq!(index.compact().expect("Should not error"), 0);
+    }
+
+    #[test]
+    fn it_rejects_compacting_a_loaded_index_with_unknown_static_config() {
+        let n = 10;
+        let d: usize = 960;
+        let distance_function = DistanceFunction::Euclidean;
+        let tmp_dir = tempdir().unwrap();
+        let persist_path = tmp_dir.path().to_str().unwrap().to_string();
+        let id = Uuid::new_v4();
+        let index = HnswIndex::init(
+            &IndexConfig {
+                dimensionality: d as i32,
+                distance_function: distance_function.clone(),
+            },
+            Some(&HnswIndexConfig {
+                max_elements: n,
+                m: 16,
+                ef_construction: 100,
+                ef_search: 10,
+                random_seed: 0,
+                persist_path: Some(persist_path.clone()),
+            }),
+            IndexUuid(id),
+        )
+        .expect("Error initializing index");
+
+        let data: Vec<f32> = utils::generate_random_data(n, d);
+        (0..n).for_each(|i| {
+            let data = &data[i * d..(i + 1) * d];
+            index.add(i, data).expect("Should not error");
+        });
+        index.save().expect("Should not error");
+
+        // Simulate a persist_path written before static config persistence
+        // was added by removing its sidecar before loading.
+        std::fs::remove_file(Path::new(&persist_path).join(STATIC_CONFIG_FILE_NAME)).unwrap();
+
+        let mut loaded = HnswIndex::load(
+            &persist_path,
+            &IndexConfig {
+                dimensionality: d as i32,
+                distance_function,
+            },
+            IndexUuid(id),
+        )
+        .expect("Should not error");
+
+        loaded.delete(0).unwrap();
+        let result = loaded.compact();
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("static config"));
+    }
 }